@@ -21,7 +21,11 @@ use leptos::{
     ssr::render_to_stream_with_prefix_undisposed_with_context_and_block_replacement,
     *,
 };
-use leptos_integration_utils::{build_async_response, html_parts_separated};
+pub use leptos_integration_utils::NoFormRedirect;
+use leptos_integration_utils::{
+    build_async_response, html_parts_separated, is_form_request,
+    referrer_redirect_location, server_fn_request_error, SERVER_FN_VARY,
+};
 use leptos_meta::*;
 use leptos_router::*;
 use parking_lot::RwLock;
@@ -110,6 +114,26 @@ pub fn redirect(cx: leptos::Scope, path: &str) {
     }
 }
 
+/// Like [redirect], but only redirects if the server function was called by a `<form>` submission
+/// or a navigation. A server function called from `fetch()` or another API client returns its data as usual.
+#[tracing::instrument(level = "trace", fields(error), skip_all)]
+pub fn redirect_form(cx: leptos::Scope, path: &str) {
+    if let Some(req) = use_context::<HttpRequest>(cx) {
+        let get = |name: header::HeaderName| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        if is_form_request(
+            get(header::ACCEPT),
+            get(header::REFERER),
+            get(header::CONTENT_TYPE),
+        ) {
+            redirect(cx, path);
+        }
+    }
+}
+
 /// An Actix [Route](actix_web::Route) that listens for a `POST` request with
 /// Leptos server function arguments in the body, runs the server function if found,
 /// and returns the resulting [HttpResponse].
@@ -144,6 +168,11 @@ pub fn redirect(cx: leptos::Scope, path: &str) {
 /// # }
 /// ```
 ///
+/// Requests that look like a `<form>` submission are redirected back to their
+/// `Referer` (see [is_form_request](leptos_integration_utils::is_form_request)),
+/// and requests the server function can't take are rejected (see
+/// [server_fn_request_error](leptos_integration_utils::server_fn_request_error)).
+///
 /// ## Provided Context Types
 /// This function always provides context values including the following types:
 /// - [ResponseOptions]
@@ -164,6 +193,14 @@ pub fn handle_server_fns() -> Route {
 /// This version allows you to pass in a closure that adds additional route data to the
 /// context, allowing you to pass in info about the route or user from Actix, or other info.
 ///
+/// Requests that look like a `<form>` submission are redirected back to their
+/// `Referer` (see [is_form_request](leptos_integration_utils::is_form_request)),
+/// and requests the server function can't take are rejected (see
+/// [server_fn_request_error](leptos_integration_utils::server_fn_request_error)).
+///
+/// To always answer with the data, even for requests that look like a `<form>`
+/// submission, provide [NoFormRedirect] from `additional_context`.
+///
 /// ## Provided Context Types
 /// This function always provides context values including the following types:
 /// - [ResponseOptions]
@@ -184,7 +221,20 @@ pub fn handle_server_fns_with_context(
                     .get("Accept")
                     .and_then(|value| value.to_str().ok());
 
-                if let Some(server_fn) = server_fn_by_path(path.as_str()) {
+                let server_fn = server_fn_by_path(path.as_str());
+                if let Some(error) = server_fn.as_ref().and_then(|server_fn| {
+                    server_fn_request_error(
+                        req.method(),
+                        req.query_string(),
+                        &server_fn.encoding(),
+                    )
+                }) {
+                    let mut res = HttpResponse::build(error.status());
+                    for (k, v) in error.headers() {
+                        res.append_header((k.clone(), v.clone()));
+                    }
+                    res.body(error.into_body())
+                } else if let Some(server_fn) = server_fn {
                     let body_ref: &[u8] = &body;
 
                     let runtime = create_runtime();
@@ -226,23 +276,46 @@ pub fn handle_server_fns_with_context(
                             let mut res: HttpResponseBuilder;
                             let res_parts = res_options.0.write();
 
-                            if accept_header == Some("application/json")
-                                || accept_header
-                                    == Some("application/x-www-form-urlencoded")
-                                || accept_header == Some("application/cbor")
+                            let form_request = is_form_request(
+                                accept_header,
+                                req.headers()
+                                    .get(header::REFERER)
+                                    .and_then(|value| value.to_str().ok()),
+                                req.headers()
+                                    .get(header::CONTENT_TYPE)
+                                    .and_then(|value| value.to_str().ok()),
+                            );
+                            if !form_request
+                                || use_context::<NoFormRedirect>(cx).is_some()
                             {
                                 res = HttpResponse::Ok();
                             }
                             // otherwise, it's probably a <form> submit or something: redirect back to the referrer
                             else {
-                                let referer = req
+                                let host = req
                                     .headers()
-                                    .get("Referer")
+                                    .get(header::HOST)
                                     .and_then(|value| value.to_str().ok())
-                                    .unwrap_or("/");
+                                    .or_else(|| {
+                                        req.uri()
+                                            .authority()
+                                            .map(|a| a.as_str())
+                                    });
+                                let referer = referrer_redirect_location(
+                                    req.headers()
+                                        .get("Referer")
+                                        .and_then(|value| value.to_str().ok()),
+                                    host,
+                                );
                                 res = HttpResponse::SeeOther();
-                                res.insert_header(("Location", referer))
-                                    .content_type("application/json");
+                                // a Location set by the server function takes
+                                // precedence over the default referrer redirect
+                                if !res_parts
+                                    .headers
+                                    .contains_key(header::LOCATION)
+                                {
+                                    res.insert_header(("Location", referer));
+                                }
                             };
                             // Override StatusCode if it was set in a Resource or Element
                             if let Some(status) = res_parts.status {
@@ -258,28 +331,47 @@ pub fn handle_server_fns_with_context(
                                     res.append_header((k, v));
                                 })
                                 .count();
+                            res.append_header((header::VARY, SERVER_FN_VARY));
 
-                            match serialized {
+                            let (content_type, body) = match serialized {
                                 Payload::Binary(data) => {
-                                    res.content_type("application/cbor");
-                                    res.body(Bytes::from(data))
-                                }
-                                Payload::Url(data) => {
-                                    res.content_type(
-                                        "application/x-www-form-urlencoded",
-                                    );
-                                    res.body(data)
+                                    ("application/cbor", Bytes::from(data))
                                 }
+                                Payload::Url(data) => (
+                                    "application/x-www-form-urlencoded",
+                                    Bytes::from(data),
+                                ),
                                 Payload::Json(data) => {
-                                    res.content_type("application/json");
-                                    res.body(data)
+                                    ("application/json", Bytes::from(data))
                                 }
+                            };
+                            // A Content-Type set by the server function takes
+                            // precedence over the default for the payload type
+                            if !res_parts
+                                .headers
+                                .contains_key(header::CONTENT_TYPE)
+                            {
+                                res.content_type(content_type);
                             }
+                            res.body(body)
+                        }
+                        Err(e) => {
+                            let res_parts = res_options.0.read();
+
+                            let mut res = HttpResponse::InternalServerError();
+                            // Keep any status and headers the server function
+                            // set before it returned the error
+                            if let Some(status) = res_parts.status {
+                                res.status(status);
+                            }
+                            for (k, v) in res_parts.headers.clone() {
+                                res.append_header((k, v));
+                            }
+                            res.body(
+                                serde_json::to_string(&e)
+                                    .unwrap_or_else(|_| e.to_string()),
+                            )
                         }
-                        Err(e) => HttpResponse::InternalServerError().body(
-                            serde_json::to_string(&e)
-                                .unwrap_or_else(|_| e.to_string()),
-                        ),
                     };
                     // clean up the scope
                     disposer.dispose();
@@ -0,0 +1,269 @@
+use actix_web::{
+    dev::ServiceResponse,
+    http::{header, Method, StatusCode},
+    test, App,
+};
+use leptos::{
+    leptos_server::{LeptosServerFnRegistry, Payload},
+    server_fn::{Encoding, ServerFnError, ServerFunctionRegistry},
+    *,
+};
+use leptos_actix::*;
+use leptos_integration_utils::MAX_SERVER_FN_QUERY_LEN;
+use std::{future::Future, pin::Pin};
+
+type ServerFnResult =
+    Pin<Box<dyn Future<Output = Result<Payload, ServerFnError>>>>;
+
+fn png(cx: Scope, _data: &[u8]) -> ServerFnResult {
+    let res_options = use_context::<ResponseOptions>(cx).unwrap();
+    res_options.insert_header(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("image/png"),
+    );
+    Box::pin(async { Ok(Payload::Binary(vec![0x89, b'P', b'N', b'G'])) })
+}
+
+fn form_redirect(cx: Scope, _data: &[u8]) -> ServerFnResult {
+    redirect_form(cx, "/done");
+    Box::pin(async { Ok(Payload::Json("\"ok\"".to_string())) })
+}
+
+fn rate_limited(cx: Scope, _data: &[u8]) -> ServerFnResult {
+    let res_options = use_context::<ResponseOptions>(cx).unwrap();
+    res_options.set_status(StatusCode::TOO_MANY_REQUESTS);
+    Box::pin(async { Err(ServerFnError::ServerError("slow down".to_string())) })
+}
+
+#[allow(deprecated)]
+fn register(
+    url: &'static str,
+    encoding: Encoding,
+    server_fn: fn(Scope, &[u8]) -> ServerFnResult,
+) {
+    // every test registers its own path, so this can't collide
+    <LeptosServerFnRegistry as ServerFunctionRegistry<Scope>>::register_explicit(
+        "/api", url, server_fn, encoding,
+    )
+    .unwrap();
+}
+
+async fn call(req: test::TestRequest) -> ServiceResponse {
+    let app = test::init_service(
+        App::new().route("/api/{tail:.*}", handle_server_fns()),
+    )
+    .await;
+    test::call_service(&app, req.to_request()).await
+}
+
+fn ok(_cx: Scope, _data: &[u8]) -> ServerFnResult {
+    Box::pin(async { Ok(Payload::Json("\"ok\"".to_string())) })
+}
+
+fn set_location(cx: Scope, _data: &[u8]) -> ServerFnResult {
+    let res_options = use_context::<ResponseOptions>(cx).unwrap();
+    res_options.insert_header(
+        header::LOCATION,
+        header::HeaderValue::from_static("/success"),
+    );
+    Box::pin(async { Ok(Payload::Url(String::new())) })
+}
+
+#[actix_web::test]
+async fn server_fn_location_wins_over_referer() {
+    register("set_location", Encoding::Url, set_location);
+
+    let res = call(
+        test::TestRequest::post()
+            .uri("/api/set_location")
+            .insert_header((header::HOST, "example.com"))
+            .insert_header((header::ACCEPT, "text/html"))
+            .insert_header((header::REFERER, "http://example.com/form")),
+    )
+    .await;
+
+    assert_eq!(res.status(), StatusCode::SEE_OTHER);
+    let locations: Vec<_> = res.headers().get_all(header::LOCATION).collect();
+    assert_eq!(locations, ["/success"]);
+}
+
+#[actix_web::test]
+async fn server_fn_method_is_checked() {
+    register("get_only", Encoding::GetJSON, ok);
+
+    let res = call(
+        test::TestRequest::get()
+            .uri("/api/get_only")
+            .insert_header((header::ACCEPT, "application/json")),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = call(
+        test::TestRequest::default()
+            .method(Method::HEAD)
+            .uri("/api/get_only")
+            .insert_header((header::ACCEPT, "application/json")),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = call(
+        test::TestRequest::post()
+            .uri("/api/get_only")
+            .insert_header((header::ACCEPT, "application/json")),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(
+        res.headers().get(header::ALLOW).unwrap(),
+        "GET, HEAD, OPTIONS"
+    );
+}
+
+#[actix_web::test]
+async fn options_lists_the_allowed_methods() {
+    register("options_post", Encoding::Url, ok);
+    register("options_get", Encoding::GetJSON, ok);
+
+    for (path, allow) in [
+        ("/api/options_post", "POST, OPTIONS"),
+        ("/api/options_get", "GET, HEAD, OPTIONS"),
+    ] {
+        let res = call(
+            test::TestRequest::default()
+                .method(Method::OPTIONS)
+                .uri(path),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(res.headers().get(header::ALLOW).unwrap(), allow);
+    }
+}
+
+#[actix_web::test]
+async fn long_get_query_is_rejected() {
+    register("long_query", Encoding::GetJSON, ok);
+
+    let query = "a".repeat(MAX_SERVER_FN_QUERY_LEN + 1);
+    let res = call(
+        test::TestRequest::get()
+            .uri(&format!("/api/long_query?{query}"))
+            .insert_header((header::ACCEPT, "application/json")),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::URI_TOO_LONG);
+}
+
+#[actix_web::test]
+async fn server_fn_content_type_wins_over_default() {
+    register("png", Encoding::Cbor, png);
+
+    let res = call(
+        test::TestRequest::post()
+            .uri("/api/png")
+            .insert_header((header::ACCEPT, "application/cbor")),
+    )
+    .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let content_types: Vec<_> =
+        res.headers().get_all(header::CONTENT_TYPE).collect();
+    assert_eq!(content_types, ["image/png"]);
+    let body = test::read_body(res).await;
+    assert_eq!(&body[..], [0x89, b'P', b'N', b'G']);
+}
+
+#[actix_web::test]
+async fn redirect_form_only_redirects_form_requests() {
+    register("form_redirect", Encoding::Url, form_redirect);
+
+    let res = call(
+        test::TestRequest::post()
+            .uri("/api/form_redirect")
+            .insert_header((header::ACCEPT, "text/html")),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::FOUND);
+    assert_eq!(res.headers().get(header::LOCATION).unwrap(), "/done");
+
+    let res = call(
+        test::TestRequest::post()
+            .uri("/api/form_redirect")
+            .insert_header((header::ACCEPT, "application/json")),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(!res.headers().contains_key(header::LOCATION));
+}
+
+#[actix_web::test]
+async fn server_fn_status_is_kept_on_error() {
+    register("rate_limited", Encoding::Url, rate_limited);
+
+    let res = call(
+        test::TestRequest::post()
+            .uri("/api/rate_limited")
+            .insert_header((header::ACCEPT, "application/json")),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[actix_web::test]
+async fn vary_lists_the_headers_the_response_depends_on() {
+    register("vary", Encoding::Url, ok);
+
+    let res = call(
+        test::TestRequest::post()
+            .uri("/api/vary")
+            .insert_header((header::ACCEPT, "application/json")),
+    )
+    .await;
+    let vary: Vec<_> = res.headers().get_all(header::VARY).collect();
+    assert_eq!(vary, ["Accept, Content-Type, Referer"]);
+}
+
+#[actix_web::test]
+async fn no_form_redirect_returns_data() {
+    register("no_form_redirect", Encoding::Url, ok);
+
+    let app = test::init_service(App::new().route(
+        "/api/{tail:.*}",
+        handle_server_fns_with_context(|cx| {
+            provide_context(cx, NoFormRedirect)
+        }),
+    ))
+    .await;
+    let req = test::TestRequest::post()
+        .uri("/api/no_form_redirect")
+        .insert_header((header::ACCEPT, "text/html"))
+        .insert_header((header::REFERER, "/form"));
+    let res = test::call_service(&app, req.to_request()).await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = test::read_body(res).await;
+    assert_eq!(&body[..], b"\"ok\"");
+}
+
+#[actix_web::test]
+async fn api_call_without_accept_gets_data() {
+    register("no_accept", Encoding::Url, ok);
+
+    for accept in [None, Some("*/*")] {
+        let mut req = test::TestRequest::post()
+            .uri("/api/no_accept")
+            .insert_header((
+                header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            ));
+        if let Some(accept) = accept {
+            req = req.insert_header((header::ACCEPT, accept));
+        }
+        let res = call(req).await;
+
+        assert_eq!(res.status(), StatusCode::OK, "Accept {accept:?}");
+        let body = test::read_body(res).await;
+        assert_eq!(&body[..], b"\"ok\"");
+    }
+}
@@ -30,7 +30,12 @@ use leptos::{
     ssr::*,
     *,
 };
-use leptos_integration_utils::{build_async_response, html_parts_separated};
+pub use leptos_integration_utils::NoFormRedirect;
+use leptos_integration_utils::{
+    build_async_response, error_response, html_parts_separated,
+    is_form_request, referrer_redirect_location, server_fn_request_error,
+    SERVER_FN_VARY,
+};
 use leptos_meta::{generate_head_metadata_separated, MetaContext};
 use leptos_router::*;
 use once_cell::sync::OnceCell;
@@ -129,6 +134,23 @@ pub fn redirect(cx: leptos::Scope, path: &str) {
     }
 }
 
+/// Like [redirect], but only redirects if the server function was called by a `<form>` submission
+/// or a navigation. A server function called from `fetch()` or another API client returns its data as usual.
+pub fn redirect_form(cx: leptos::Scope, path: &str) {
+    if let Some(req) = use_context::<RequestParts>(cx) {
+        let get = |name: header::HeaderName| {
+            req.headers.get(name).and_then(|value| value.to_str().ok())
+        };
+        if is_form_request(
+            get(header::ACCEPT),
+            get(header::REFERER),
+            get(header::CONTENT_TYPE),
+        ) {
+            redirect(cx, path);
+        }
+    }
+}
+
 /// Decomposes an HTTP request into its parts, allowing you to read its headers
 /// and other data without consuming the body. Creates a new Request from the
 /// original parts for further processing
@@ -181,6 +203,11 @@ pub async fn generate_request_and_parts(
 /// Leptos provides a generic implementation of `handle_server_fns`. If access to more specific parts of the Request is desired,
 /// you can specify your own server fn handler based on this one and give it it's own route in the server macro.
 ///
+/// Requests that look like a `<form>` submission are redirected back to their
+/// `Referer` (see [is_form_request](leptos_integration_utils::is_form_request)),
+/// and requests the server function can't take are rejected (see
+/// [server_fn_request_error](leptos_integration_utils::server_fn_request_error)).
+///
 /// ## Provided Context Types
 /// This function always provides context values including the following types:
 /// - [RequestParts]
@@ -205,6 +232,14 @@ pub async fn handle_server_fns(
 /// that takes in the data you'd like. See the [render_app_to_stream_with_context] docs for an example
 /// of one that should work much like this one.
 ///
+/// Requests that look like a `<form>` submission are redirected back to their
+/// `Referer` (see [is_form_request](leptos_integration_utils::is_form_request)),
+/// and requests the server function can't take are rejected (see
+/// [server_fn_request_error](leptos_integration_utils::server_fn_request_error)).
+///
+/// To always answer with the data, even for requests that look like a `<form>`
+/// submission, provide [NoFormRedirect] from `additional_context`.
+///
 /// ## Provided Context Types
 /// This function always provides context values including the following types:
 /// - [RequestParts]
@@ -238,9 +273,17 @@ async fn handle_server_fns_inner(
     let pool_handle = get_leptos_pool();
     pool_handle.spawn_pinned(move || {
         async move {
-            let res = if let Some(server_fn) =
-                server_fn_by_path(fn_name.as_str())
-            {
+            let server_fn = server_fn_by_path(fn_name.as_str());
+            let res = if let Some(res) =
+                server_fn.as_ref().and_then(|server_fn| {
+                    server_fn_request_error(
+                        req.method(),
+                        req.uri().query().unwrap_or_default(),
+                        &server_fn.encoding(),
+                    )
+                }) {
+                Ok(res.map(Full::from))
+            } else if let Some(server_fn) = server_fn {
                 let runtime = create_runtime();
                 let (cx, disposer) = raw_scope_and_disposer(runtime);
 
@@ -277,19 +320,37 @@ async fn handle_server_fns_inner(
                             res_options_inner.headers.clone(),
                         );
 
-                        if accept_header == Some("application/json")
-                            || accept_header
-                                == Some("application/x-www-form-urlencoded")
-                            || accept_header == Some("application/cbor")
+                        let form_request = is_form_request(
+                            accept_header,
+                            headers
+                                .get(header::REFERER)
+                                .and_then(|value| value.to_str().ok()),
+                            headers
+                                .get(header::CONTENT_TYPE)
+                                .and_then(|value| value.to_str().ok()),
+                        );
+                        if !form_request
+                            || use_context::<NoFormRedirect>(cx).is_some()
                         {
                             res = res.status(StatusCode::OK);
                         }
                         // otherwise, it's probably a <form> submit or something: redirect back to the referrer
                         else {
-                            let referer = headers
-                                .get("Referer")
+                            let host = headers
+                                .get(header::HOST)
                                 .and_then(|value| value.to_str().ok())
-                                .unwrap_or("/");
+                                .or_else(|| {
+                                    req_parts
+                                        .uri
+                                        .authority()
+                                        .map(|a| a.as_str())
+                                });
+                            let referer = referrer_redirect_location(
+                                headers
+                                    .get("Referer")
+                                    .and_then(|value| value.to_str().ok()),
+                                host,
+                            );
 
                             res = res
                                 .status(StatusCode::SEE_OTHER)
@@ -300,48 +361,79 @@ async fn handle_server_fns_inner(
                             Some(status) => res.status(status),
                             None => res,
                         };
+                        let (content_type, body) = match serialized {
+                            Payload::Binary(data) => {
+                                ("application/cbor", Full::from(data))
+                            }
+                            Payload::Url(data) => (
+                                "application/x-www-form-urlencoded",
+                                Full::from(data),
+                            ),
+                            Payload::Json(data) => {
+                                ("application/json", Full::from(data))
+                            }
+                        };
+                        // A Content-Type set by the server function takes precedence
+                        // over the default for the payload type
+                        res_headers
+                            .entry(header::CONTENT_TYPE)
+                            .or_insert(HeaderValue::from_static(content_type));
                         // This must be after the default referrer
                         // redirect so that it overwrites the one above
                         if let Some(header_ref) = res.headers_mut() {
                             header_ref.extend(res_headers.drain());
+                            header_ref.append(
+                                header::VARY,
+                                HeaderValue::from_static(SERVER_FN_VARY),
+                            );
                         };
-                        match serialized {
-                            Payload::Binary(data) => res
-                                .header("Content-Type", "application/cbor")
-                                .body(Full::from(data)),
-                            Payload::Url(data) => res
-                                .header(
-                                    "Content-Type",
-                                    "application/x-www-form-urlencoded",
-                                )
-                                .body(Full::from(data)),
-                            Payload::Json(data) => res
-                                .header("Content-Type", "application/json")
-                                .body(Full::from(data)),
-                        }
+                        res.body(body)
                     }
-                    Err(e) => Response::builder()
-                        .status(StatusCode::INTERNAL_SERVER_ERROR)
-                        .body(Full::from(
+                    Err(e) => {
+                        let res_options_outer =
+                            use_context::<ResponseOptions>(cx).unwrap().0;
+                        let res_options_inner = res_options_outer.read();
+
+                        // Keep any status and headers the server function set
+                        // before it returned the error
+                        let mut res = Response::builder().status(
+                            res_options_inner
+                                .status
+                                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                        );
+                        if let Some(header_ref) = res.headers_mut() {
+                            header_ref
+                                .extend(res_options_inner.headers.clone());
+                        };
+                        res.body(Full::from(
                             serde_json::to_string(&e)
                                 .unwrap_or_else(|_| e.to_string()),
-                        )),
+                        ))
+                    }
                 };
                 // clean up the scope
                 disposer.dispose();
                 runtime.dispose();
                 res
             } else {
-                Response::builder().status(StatusCode::BAD_REQUEST).body(
+                Ok(error_response(
+                    StatusCode::BAD_REQUEST,
                     Full::from(format!(
                         "Could not find a server function at the route \
                          {fn_name}. \n\nIt's likely that you need to call \
                          ServerFn::register_explicit() on the server function \
                          type, somewhere in your `main` function."
                     )),
-                )
+                ))
             }
-            .expect("could not build Response");
+            // a header set by the server function can make the builder fail;
+            // answer with a 500 instead of panicking
+            .unwrap_or_else(|e| {
+                error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Full::from(e.to_string()),
+                )
+            });
 
             _ = tx.send(res);
         }
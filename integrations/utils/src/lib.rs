@@ -1,5 +1,6 @@
 use futures::{Stream, StreamExt};
-use leptos::{use_context, RuntimeId, ScopeId};
+use http::{header, HeaderValue, Method, Response, StatusCode};
+use leptos::{server_fn::Encoding, use_context, RuntimeId, ScopeId};
 use leptos_config::LeptosOptions;
 use leptos_meta::MetaContext;
 
@@ -151,3 +152,317 @@ pub async fn build_async_response(
 
     format!("{head}{head_meta}</head><body{body_meta}>{buf}{tail}")
 }
+
+/// Builds a response with the given status and body.
+///
+/// Unlike going through a response builder, this can't fail, so it's safe to
+/// use on the error paths of the server function handlers.
+pub fn error_response<B>(status: StatusCode, body: B) -> Response<B> {
+    let mut res = Response::new(body);
+    *res.status_mut() = status;
+    res
+}
+
+/// Returns the HTTP method a server function with the given encoding has to be
+/// called with.
+///
+/// `GetJSON` and `GetCBOR` server functions take their arguments from the
+/// query string and are called with `GET`, while `Url` and `Cbor` ones take
+/// them from the body and are called with `POST`.
+pub fn server_fn_method(encoding: &Encoding) -> Method {
+    match encoding {
+        Encoding::Url | Encoding::Cbor => Method::POST,
+        Encoding::GetJSON | Encoding::GetCBOR => Method::GET,
+    }
+}
+
+/// The longest query string [server_fn_request_error] accepts for a `GET`
+/// server function. Servers and proxies along the way commonly cut off URLs
+/// not much longer than this.
+pub const MAX_SERVER_FN_QUERY_LEN: usize = 8 * 1024;
+
+/// Returns the `Allow` header of a server function with the given encoding:
+/// its [server_fn_method], plus `HEAD` for a `GET` server function, which is
+/// answered like `GET` without the body, and `OPTIONS`.
+pub fn server_fn_allow(encoding: &Encoding) -> &'static str {
+    if server_fn_method(encoding) == Method::GET {
+        "GET, HEAD, OPTIONS"
+    } else {
+        "POST, OPTIONS"
+    }
+}
+
+/// Checks whether a request can be passed on to a server function with the
+/// given encoding, returning the response to send instead if it can't.
+///
+/// An `OPTIONS` request gets a `204 No Content` listing the [server_fn_allow]
+/// methods in its `Allow` header, and a request with any other method the
+/// server function doesn't accept gets a `405 Method Not Allowed` with the same
+/// header. A `GET` server function whose query is longer than
+/// [MAX_SERVER_FN_QUERY_LEN] gets a `414 URI Too Long`.
+pub fn server_fn_request_error(
+    method: &Method,
+    query: &str,
+    encoding: &Encoding,
+) -> Option<Response<String>> {
+    let expected = server_fn_method(encoding);
+    if *method == expected
+        || (expected == Method::GET && *method == Method::HEAD)
+    {
+        if expected == Method::GET && query.len() > MAX_SERVER_FN_QUERY_LEN {
+            return Some(error_response(
+                StatusCode::URI_TOO_LONG,
+                format!(
+                    "The arguments of this server function don't fit in a \
+                     URL of at most {MAX_SERVER_FN_QUERY_LEN} bytes. Use a \
+                     POST encoding like `Url` or `Cbor` for large arguments."
+                ),
+            ));
+        }
+        return None;
+    }
+    let mut res = if *method == Method::OPTIONS {
+        error_response(StatusCode::NO_CONTENT, String::new())
+    } else {
+        error_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            format!("This server function has to be called with {expected}."),
+        )
+    };
+    res.headers_mut().insert(
+        header::ALLOW,
+        HeaderValue::from_static(server_fn_allow(encoding)),
+    );
+    Some(res)
+}
+
+/// Provide this as context, for example from the `additional_context` of
+/// `handle_server_fns_with_context`, to always answer server function calls
+/// with their data. A request that looks like a `<form>` submission then gets a
+/// `200` instead of the default redirect back to its `Referer`. A redirect the
+/// server function sets up itself still applies.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoFormRedirect;
+
+/// The `Vary` header of a server function response. It lists the request
+/// headers [is_form_request] looks at: they decide between a redirect and the
+/// data, so caches must not serve a response for a request that differs in
+/// them.
+pub const SERVER_FN_VARY: &str = "Accept, Content-Type, Referer";
+
+/// Returns whether a request to a server function clearly looks like a browser
+/// `<form>` submission or navigation, rather than an API call that wants the
+/// server function's data back.
+///
+/// The server function handlers redirect form requests back to the page they
+/// came from, and answer everything else with the data. A request counts as a
+/// form request if its `Accept` header isn't one of the encodings the server
+/// function client asks for (`application/json`,
+/// `application/x-www-form-urlencoded` or `application/cbor`), and it either
+/// - accepts `text/html`, or
+/// - has a `Referer` and a form `Content-Type`
+///   (`application/x-www-form-urlencoded` or `multipart/form-data`).
+///
+/// So a request without an `Accept` header, or with curl's `Accept: */*`, gets
+/// the data unless it also carries a `Referer`, as a browser would.
+pub fn is_form_request(
+    accept: Option<&str>,
+    referer: Option<&str>,
+    content_type: Option<&str>,
+) -> bool {
+    let accept = accept.unwrap_or_default();
+    // the server function client sends a form Content-Type and, from a
+    // browser, a Referer too, so its Accept header has to be checked first
+    if matches!(
+        accept,
+        "application/json"
+            | "application/x-www-form-urlencoded"
+            | "application/cbor"
+    ) {
+        return false;
+    }
+    let mime = content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .unwrap_or_default()
+        .trim();
+    let has_form_content_type = mime
+        .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+        || mime.eq_ignore_ascii_case("multipart/form-data");
+    accept.contains("text/html") || (referer.is_some() && has_form_content_type)
+}
+
+/// Returns the location a successful `<form>` submission to a server function
+/// should be redirected back to.
+///
+/// The `Referer` is only honored if it is a path on the current origin, or an
+/// absolute URL whose host matches the `Host` of the request. Any other value
+/// (including a missing `Referer`) falls back to `/`, so a forged `Referer`
+/// can't turn the redirect into an open redirect to another site.
+///
+/// Browsers send an absolute `Referer`, so this relies on `Host` being the
+/// address the browser used. Behind a reverse proxy that passes on its own
+/// internal address instead, every redirect falls back to `/`; configure the
+/// proxy to forward the original `Host` (e.g. `proxy_set_header Host $host` in
+/// nginx).
+pub fn referrer_redirect_location<'a>(
+    referer: Option<&'a str>,
+    host: Option<&str>,
+) -> &'a str {
+    match referer {
+        Some(referer) if is_same_origin(referer, host) => referer,
+        _ => "/",
+    }
+}
+
+fn is_same_origin(referer: &str, host: Option<&str>) -> bool {
+    // a path on this origin, but not a protocol-relative `//other.host` URL
+    if referer.starts_with('/') {
+        return !referer.starts_with("//") && !referer.starts_with("/\\");
+    }
+    let authority = referer
+        .strip_prefix("https://")
+        .or_else(|| referer.strip_prefix("http://"))
+        .and_then(|rest| rest.split(['/', '?', '#', '\\']).next());
+    match (authority, host) {
+        (Some(authority), Some(host)) => authority.eq_ignore_ascii_case(host),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_response_has_status_and_body() {
+        for status in
+            [StatusCode::BAD_REQUEST, StatusCode::INTERNAL_SERVER_ERROR]
+        {
+            let res = error_response(status, "something went wrong");
+            assert_eq!(res.status(), status);
+            assert_eq!(*res.body(), "something went wrong");
+        }
+    }
+
+    #[test]
+    fn server_fn_request_error_checks_method() {
+        for (encoding, method) in [
+            (Encoding::Url, Method::POST),
+            (Encoding::Cbor, Method::POST),
+            (Encoding::GetJSON, Method::GET),
+            (Encoding::GetCBOR, Method::GET),
+        ] {
+            assert_eq!(server_fn_method(&encoding), method);
+            assert!(server_fn_request_error(&method, "", &encoding).is_none());
+        }
+        for encoding in [Encoding::GetJSON, Encoding::GetCBOR] {
+            assert!(
+                server_fn_request_error(&Method::HEAD, "", &encoding).is_none()
+            );
+        }
+
+        let res =
+            server_fn_request_error(&Method::POST, "", &Encoding::GetJSON)
+                .unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "GET, HEAD, OPTIONS");
+
+        for method in [Method::GET, Method::HEAD] {
+            let res =
+                server_fn_request_error(&method, "", &Encoding::Url).unwrap();
+            assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+            assert_eq!(res.headers()[header::ALLOW], "POST, OPTIONS");
+        }
+    }
+
+    #[test]
+    fn server_fn_request_error_answers_options() {
+        for (encoding, allow) in [
+            (Encoding::Url, "POST, OPTIONS"),
+            (Encoding::GetCBOR, "GET, HEAD, OPTIONS"),
+        ] {
+            let res = server_fn_request_error(&Method::OPTIONS, "", &encoding)
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::NO_CONTENT);
+            assert_eq!(res.headers()[header::ALLOW], allow);
+            assert!(res.body().is_empty());
+        }
+    }
+
+    #[test]
+    fn server_fn_request_error_checks_query_len() {
+        let query = "a".repeat(MAX_SERVER_FN_QUERY_LEN);
+        assert!(server_fn_request_error(
+            &Method::GET,
+            &query,
+            &Encoding::GetJSON
+        )
+        .is_none());
+
+        let query = "a".repeat(MAX_SERVER_FN_QUERY_LEN + 1);
+        let res =
+            server_fn_request_error(&Method::GET, &query, &Encoding::GetCBOR)
+                .unwrap();
+        assert_eq!(res.status(), StatusCode::URI_TOO_LONG);
+        // only GET server functions take their arguments from the query
+        assert!(
+            server_fn_request_error(&Method::POST, &query, &Encoding::Url)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn form_requests_are_told_apart_from_api_calls() {
+        const FORM: Option<&str> = Some("application/x-www-form-urlencoded");
+        let referer = Some("/form");
+        let browser_accept = Some("text/html,application/xhtml+xml,*/*;q=0.8");
+
+        // browser <form> submissions and navigations
+        assert!(is_form_request(browser_accept, referer, FORM));
+        assert!(is_form_request(browser_accept, None, None));
+        assert!(is_form_request(
+            Some("*/*"),
+            referer,
+            Some("multipart/form-data; boundary=x")
+        ));
+
+        // the server function client, whatever else it sends
+        for accept in [
+            "application/json",
+            "application/x-www-form-urlencoded",
+            "application/cbor",
+        ] {
+            assert!(!is_form_request(Some(accept), referer, FORM));
+        }
+
+        // curl and other API clients
+        assert!(!is_form_request(None, None, FORM));
+        assert!(!is_form_request(Some("*/*"), None, FORM));
+        assert!(!is_form_request(
+            Some("*/*"),
+            referer,
+            Some("application/json")
+        ));
+    }
+
+    #[test]
+    fn referrer_redirect_only_honors_same_origin() {
+        let host = Some("host");
+        for (referer, location) in [
+            ("/path", "/path"),
+            ("//evil.com", "/"),
+            ("/\\evil.com", "/"),
+            ("https://host/x", "https://host/x"),
+            ("https://evil/x", "/"),
+            ("https://host@evil/", "/"),
+            ("https://host:8080/x", "/"),
+        ] {
+            assert_eq!(
+                referrer_redirect_location(Some(referer), host),
+                location,
+                "Referer {referer:?}"
+            );
+        }
+        assert_eq!(referrer_redirect_location(None, host), "/");
+    }
+}
@@ -0,0 +1,278 @@
+use leptos::{
+    leptos_server::{LeptosServerFnRegistry, Payload},
+    server_fn::{Encoding, ServerFnError, ServerFunctionRegistry},
+    *,
+};
+use leptos_integration_utils::MAX_SERVER_FN_QUERY_LEN;
+use leptos_viz::*;
+use std::{future::Future, pin::Pin, sync::Arc};
+use viz::{
+    header,
+    headers::HeaderValue,
+    types::{Params, RouteInfo},
+    Body, Method, Request, Response, StatusCode,
+};
+
+type ServerFnResult =
+    Pin<Box<dyn Future<Output = Result<Payload, ServerFnError>>>>;
+
+fn png(cx: Scope, _data: &[u8]) -> ServerFnResult {
+    let res_options = use_context::<ResponseOptions>(cx).unwrap();
+    res_options.insert_header(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("image/png"),
+    );
+    Box::pin(async { Ok(Payload::Binary(vec![0x89, b'P', b'N', b'G'])) })
+}
+
+fn form_redirect(cx: Scope, _data: &[u8]) -> ServerFnResult {
+    redirect_form(cx, "/done");
+    Box::pin(async { Ok(Payload::Json("\"ok\"".to_string())) })
+}
+
+fn rate_limited(cx: Scope, _data: &[u8]) -> ServerFnResult {
+    let res_options = use_context::<ResponseOptions>(cx).unwrap();
+    res_options.set_status(StatusCode::TOO_MANY_REQUESTS);
+    Box::pin(async { Err(ServerFnError::ServerError("slow down".to_string())) })
+}
+
+#[allow(deprecated)]
+fn register(
+    url: &'static str,
+    encoding: Encoding,
+    server_fn: fn(Scope, &[u8]) -> ServerFnResult,
+) {
+    // every test registers its own path, so this can't collide
+    <LeptosServerFnRegistry as ServerFunctionRegistry<Scope>>::register_explicit(
+        "/api", url, server_fn, encoding,
+    )
+    .unwrap();
+}
+
+fn route(mut req: Request) -> Request {
+    // what the router would do for a `/api/:fn_name*` route
+    let fn_name = req.uri().path().trim_start_matches("/api/").to_string();
+    req.extensions_mut().insert(Arc::new(RouteInfo {
+        id: 0,
+        pattern: "/api/:fn_name*".to_string(),
+        params: Params(vec![("fn_name".to_string(), fn_name)]),
+    }));
+    req
+}
+
+async fn call(req: Request) -> Response {
+    handle_server_fns(route(req)).await.unwrap()
+}
+
+fn ok(_cx: Scope, _data: &[u8]) -> ServerFnResult {
+    Box::pin(async { Ok(Payload::Json("\"ok\"".to_string())) })
+}
+
+fn set_location(cx: Scope, _data: &[u8]) -> ServerFnResult {
+    let res_options = use_context::<ResponseOptions>(cx).unwrap();
+    res_options
+        .insert_header(header::LOCATION, HeaderValue::from_static("/success"));
+    Box::pin(async { Ok(Payload::Url(String::new())) })
+}
+
+#[tokio::test]
+async fn server_fn_location_wins_over_referer() {
+    register("set_location", Encoding::Url, set_location);
+
+    let res = call(
+        Request::post("/api/set_location")
+            .header(header::HOST, "example.com")
+            .header(header::ACCEPT, "text/html")
+            .header(header::REFERER, "http://example.com/form")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+
+    assert_eq!(res.status(), StatusCode::SEE_OTHER);
+    let locations: Vec<_> =
+        res.headers().get_all(header::LOCATION).iter().collect();
+    assert_eq!(locations, ["/success"]);
+}
+
+#[tokio::test]
+async fn server_fn_method_is_checked() {
+    register("get_only", Encoding::GetJSON, ok);
+
+    let res = call(
+        Request::get("/api/get_only")
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = call(
+        Request::head("/api/get_only")
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = call(
+        Request::post("/api/get_only")
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.headers()[header::ALLOW], "GET, HEAD, OPTIONS");
+}
+
+#[tokio::test]
+async fn options_lists_the_allowed_methods() {
+    register("options_post", Encoding::Url, ok);
+    register("options_get", Encoding::GetJSON, ok);
+
+    for (path, allow) in [
+        ("/api/options_post", "POST, OPTIONS"),
+        ("/api/options_get", "GET, HEAD, OPTIONS"),
+    ] {
+        let res = call(
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri(path)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(res.headers()[header::ALLOW], allow);
+    }
+}
+
+#[tokio::test]
+async fn long_get_query_is_rejected() {
+    register("long_query", Encoding::GetJSON, ok);
+
+    let query = "a".repeat(MAX_SERVER_FN_QUERY_LEN + 1);
+    let res = call(
+        Request::get(format!("/api/long_query?{query}"))
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::URI_TOO_LONG);
+}
+
+#[tokio::test]
+async fn server_fn_content_type_wins_over_default() {
+    register("png", Encoding::Cbor, png);
+
+    let res = call(
+        Request::post("/api/png")
+            .header(header::ACCEPT, "application/cbor")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let content_types: Vec<_> =
+        res.headers().get_all(header::CONTENT_TYPE).iter().collect();
+    assert_eq!(content_types, ["image/png"]);
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    assert_eq!(&body[..], [0x89, b'P', b'N', b'G']);
+}
+
+#[tokio::test]
+async fn redirect_form_only_redirects_form_requests() {
+    register("form_redirect", Encoding::Url, form_redirect);
+
+    let res = call(
+        Request::post("/api/form_redirect")
+            .header(header::ACCEPT, "text/html")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::FOUND);
+    assert_eq!(res.headers()[header::LOCATION], "/done");
+
+    let res = call(
+        Request::post("/api/form_redirect")
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(!res.headers().contains_key(header::LOCATION));
+}
+
+#[tokio::test]
+async fn server_fn_status_is_kept_on_error() {
+    register("rate_limited", Encoding::Url, rate_limited);
+
+    let res = call(
+        Request::post("/api/rate_limited")
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn vary_lists_the_headers_the_response_depends_on() {
+    register("vary", Encoding::Url, ok);
+
+    let res = call(
+        Request::post("/api/vary")
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    let vary: Vec<_> = res.headers().get_all(header::VARY).iter().collect();
+    assert_eq!(vary, ["Accept, Content-Type, Referer"]);
+}
+
+#[tokio::test]
+async fn no_form_redirect_returns_data() {
+    register("no_form_redirect", Encoding::Url, ok);
+
+    let req = Request::post("/api/no_form_redirect")
+        .header(header::ACCEPT, "text/html")
+        .header(header::REFERER, "/form")
+        .body(Body::empty())
+        .unwrap();
+    let res = handle_server_fns_with_context(route(req), |cx| {
+        provide_context(cx, NoFormRedirect)
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    assert_eq!(&body[..], b"\"ok\"");
+}
+
+#[tokio::test]
+async fn api_call_without_accept_gets_data() {
+    register("no_accept", Encoding::Url, ok);
+
+    for accept in [None, Some("*/*")] {
+        let mut req = Request::post("/api/no_accept")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+        if let Some(accept) = accept {
+            req = req.header(header::ACCEPT, accept);
+        }
+        let res = call(req.body(Body::empty()).unwrap()).await;
+
+        assert_eq!(res.status(), StatusCode::OK, "Accept {accept:?}");
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"\"ok\"");
+    }
+}
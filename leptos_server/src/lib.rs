@@ -283,12 +283,17 @@ pub enum ServerRegistrationFnError {
 }
 
 /// Get a ServerFunction struct containing info about the server fn
+///
+/// A single trailing slash on `path` is ignored, so `api/foo/` finds the
+/// server function registered at `api/foo`.
 #[cfg(any(feature = "ssr", doc))]
 pub fn server_fn_by_path(path: &str) -> Option<ServerFnTraitObj> {
-    REGISTERED_SERVER_FUNCTIONS
+    let registry = REGISTERED_SERVER_FUNCTIONS
         .read()
-        .expect("Server function registry is poisoned")
+        .expect("Server function registry is poisoned");
+    registry
         .get(path)
+        .or_else(|| path.strip_suffix('/').and_then(|path| registry.get(path)))
         .cloned()
 }
 
@@ -394,3 +399,33 @@ pub trait ServerFn: server_fn::ServerFn<Scope> {
 }
 
 impl<T> ServerFn for T where T: server_fn::ServerFn<Scope> {}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use server_fn::ServerFunctionRegistry;
+    use std::{future::Future, pin::Pin};
+
+    fn foo(
+        _cx: Scope,
+        _data: &[u8],
+    ) -> Pin<Box<dyn Future<Output = Result<Payload, ServerFnError>>>> {
+        Box::pin(async { Ok(Payload::Json("null".to_string())) })
+    }
+
+    #[test]
+    fn server_fn_by_path_ignores_trailing_slash() {
+        #[allow(deprecated)]
+        LeptosServerFnRegistry::register_explicit(
+            "/",
+            "api/foo",
+            foo,
+            Encoding::Url,
+        )
+        .unwrap();
+
+        assert_eq!(server_fn_by_path("api/foo").unwrap().url(), "api/foo");
+        assert_eq!(server_fn_by_path("api/foo/").unwrap().url(), "api/foo");
+        assert!(server_fn_by_path("api/foo//").is_none());
+    }
+}
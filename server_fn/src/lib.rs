@@ -377,9 +377,7 @@ where
         // decode the args
         let value = match Self::encoding() {
             Encoding::Url | Encoding::GetJSON | Encoding::GetCBOR => {
-                serde_qs::Config::new(5, false)
-                    .deserialize_bytes(data)
-                    .map_err(|e| ServerFnError::Deserialization(e.to_string()))
+                deserialize_qs(data)
             }
             Encoding::Cbor => ciborium::de::from_reader(data)
                 .map_err(|e| ServerFnError::Deserialization(e.to_string())),
@@ -453,6 +451,61 @@ where
     }
 }
 
+/// Decodes server function arguments from a query string or form body.
+#[cfg(any(feature = "ssr", doc))]
+fn deserialize_qs<T: DeserializeOwned>(
+    data: &[u8],
+) -> Result<T, ServerFnError> {
+    serde_qs::Config::new(5, false)
+        .deserialize_bytes(&bracket_repeated_keys(data))
+        .map_err(|e| ServerFnError::Deserialization(e.to_string()))
+}
+
+/// `serde_qs` only collects repeated keys into a sequence if they are written
+/// as `key[]`, but HTML forms (like a group of checkboxes) repeat the plain key:
+/// `tag=a&tag=b`. This rewrites every key that appears more than once without
+/// brackets to `key[]`, and leaves the data alone otherwise. Browsers send a
+/// field named `tag[]` as `tag%5B%5D`, so an encoded bracket counts as one.
+///
+/// A key that appears only once is left alone, since the argument types aren't
+/// known here: a single `tag=a` can't be decoded into a `Vec`, but `tag[]=a`
+/// can.
+#[cfg(any(feature = "ssr", doc))]
+fn bracket_repeated_keys(data: &[u8]) -> std::borrow::Cow<'_, [u8]> {
+    let key_len = |pair: &[u8]| -> usize {
+        pair.iter().position(|b| *b == b'=').unwrap_or(pair.len())
+    };
+    let has_bracket = |key: &[u8]| -> bool {
+        key.contains(&b'[') || key.windows(3).any(|w| w == b"%5B")
+    };
+    let pairs = data.split(|b| *b == b'&').filter(|pair| !pair.is_empty());
+
+    let mut counts = std::collections::HashMap::<&[u8], usize>::new();
+    for pair in pairs.clone() {
+        let key = &pair[..key_len(pair)];
+        if !has_bracket(key) {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+    if counts.values().all(|count| *count == 1) {
+        return data.into();
+    }
+
+    let mut bracketed = Vec::with_capacity(data.len());
+    for pair in pairs {
+        if !bracketed.is_empty() {
+            bracketed.push(b'&');
+        }
+        let (key, value) = pair.split_at(key_len(pair));
+        bracketed.extend_from_slice(key);
+        if counts.get(key).copied().unwrap_or_default() > 1 {
+            bracketed.extend_from_slice(b"[]");
+        }
+        bracketed.extend_from_slice(value);
+    }
+    bracketed.into()
+}
+
 /// Executes the HTTP call to call a server function from the client, given its URL and argument type.
 #[cfg(not(feature = "ssr"))]
 pub async fn call_server_fn<T, C: 'static>(
@@ -581,14 +634,13 @@ where
     let status = resp.status();
     #[cfg(not(target_arch = "wasm32"))]
     let status = status.as_u16();
-    if (500..=599).contains(&status) {
+    if is_error_status(status) {
         let text = resp.text().await.unwrap_or_default();
         #[cfg(target_arch = "wasm32")]
         let status_text = resp.status_text();
         #[cfg(not(target_arch = "wasm32"))]
         let status_text = status.to_string();
-        return Err(serde_json::from_str(&text)
-            .unwrap_or(ServerFnError::ServerError(status_text)));
+        return Err(decode_error_response(&text, status_text));
     }
 
     // Decoding the body of the request
@@ -622,6 +674,29 @@ where
     }
 }
 
+/// Whether a response with this status carries an error instead of the return
+/// value. The server function handlers keep a `4xx` status the server function
+/// sets along with its error, so this can't be limited to `5xx`.
+#[cfg(any(not(feature = "ssr"), test))]
+fn is_error_status(status: u16) -> bool {
+    (400..=599).contains(&status)
+}
+
+/// Decodes the body of an error response. A failing server function's
+/// [ServerFnError] is sent as JSON, whatever its encoding, while the errors
+/// the handlers answer with themselves, like a `414` for a query that's too
+/// long, explain the problem in plain text, which is kept as the message.
+#[cfg(any(not(feature = "ssr"), test))]
+fn decode_error_response(text: &str, status_text: String) -> ServerFnError {
+    serde_json::from_str(text).unwrap_or_else(|_| {
+        ServerFnError::ServerError(if text.is_empty() {
+            status_text
+        } else {
+            text.to_string()
+        })
+    })
+}
+
 // Lazily initialize the client to be reused for all server function calls.
 #[cfg(any(all(not(feature = "ssr"), not(target_arch = "wasm32")), doc))]
 static CLIENT: once_cell::sync::Lazy<reqwest::Client> =
@@ -643,3 +718,91 @@ fn get_server_url() -> &'static str {
         .get()
         .expect("Call set_root_url before calling a server function.")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "ssr")]
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Tags {
+        tag: Vec<String>,
+    }
+
+    #[cfg(feature = "ssr")]
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Search {
+        q: String,
+        page: u32,
+    }
+
+    #[test]
+    fn client_error_response_is_decoded_as_error() {
+        // what the handlers send when a server function fails with a `429`
+        let body = serde_json::to_string(&ServerFnError::ServerError(
+            "slow down".to_string(),
+        ))
+        .unwrap();
+
+        assert!(is_error_status(429));
+        assert!(matches!(
+            decode_error_response(&body, "429".to_string()),
+            ServerFnError::ServerError(msg) if msg == "slow down"
+        ));
+        assert!(!is_error_status(200));
+        assert!(!is_error_status(303));
+    }
+
+    #[test]
+    fn client_keeps_plain_text_error_message() {
+        let body = "The arguments of this server function don't fit in a URL.";
+        assert!(is_error_status(414));
+        assert!(matches!(
+            decode_error_response(body, "414".to_string()),
+            ServerFnError::ServerError(msg) if msg == body
+        ));
+        assert!(matches!(
+            decode_error_response("", "414".to_string()),
+            ServerFnError::ServerError(msg) if msg == "414"
+        ));
+    }
+
+    #[cfg(feature = "ssr")]
+    #[test]
+    fn repeated_query_keys_deserialize_into_vec() {
+        let tags: Tags = deserialize_qs(b"tag=a&tag=b&tag=c").unwrap();
+        assert_eq!(tags.tag, ["a", "b", "c"]);
+    }
+
+    #[cfg(feature = "ssr")]
+    #[test]
+    fn encoded_bracket_keys_deserialize_into_vec() {
+        // a form field named `tag[]`, as the browser sends it
+        let tags: Tags = deserialize_qs(b"tag%5B%5D=a&tag%5B%5D=b").unwrap();
+        assert_eq!(tags.tag, ["a", "b"]);
+
+        let tags: Tags = deserialize_qs(b"tag%5B%5D=a").unwrap();
+        assert_eq!(tags.tag, ["a"]);
+        let tags: Tags = deserialize_qs(b"tag[]=a").unwrap();
+        assert_eq!(tags.tag, ["a"]);
+
+        // a single plain key can't be told apart from a scalar argument
+        assert!(deserialize_qs::<Tags>(b"tag=a").is_err());
+    }
+
+    #[cfg(feature = "ssr")]
+    #[test]
+    fn serde_qs_encoded_queries_are_unchanged() {
+        let tags: Tags = deserialize_qs(b"tag[0]=a&tag[1]=b").unwrap();
+        assert_eq!(tags.tag, ["a", "b"]);
+
+        let search: Search = deserialize_qs(b"q=leptos&page=2").unwrap();
+        assert_eq!(
+            search,
+            Search {
+                q: "leptos".to_string(),
+                page: 2
+            }
+        );
+    }
+}